@@ -1,19 +1,69 @@
 use alloc::boxed::Box;
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use core::task::Waker;
 use async_trait::async_trait;
+use bitflags::bitflags;
 use crate::arch::PAGE_SIZE;
 use crate::fs::inode::Inode;
 use crate::result::{Errno, SyscallResult};
 use crate::sync::mutex::{AsyncMutex, Mutex};
 use crate::fs::ffi::OpenFlags;
+use crate::fs::lock::{FileLock, LockCmd, LockList, Pid};
+use crate::fs::notify::{EventQueue, InotifyEvent, InotifyMask, NotifySink, Wd};
 use crate::net::Socket;
 use crate::process::thread::Audit;
 
+bitflags! {
+    /// Mode flags for [`File::fallocate`], matching the `fallocate(2)` `mode` argument.
+    pub struct FallocMode: i32 {
+        const KEEP_SIZE = 0x01;
+        const PUNCH_HOLE = 0x02;
+        const COLLAPSE_RANGE = 0x08;
+        const ZERO_RANGE = 0x10;
+        const INSERT_RANGE = 0x20;
+    }
+}
+
+/// Default number of bytes the read path prefetches ahead of the current
+/// position; widened for `Advice::Sequential`/`Advice::WillNeed` and
+/// suppressed entirely for `Advice::Random`.
+const READAHEAD_WINDOW: isize = PAGE_SIZE as isize * 16;
+
+/// `posix_fadvise(2)` access pattern hint, mirroring the `POSIX_FADV_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advice {
+    Normal,
+    Sequential,
+    Random,
+    WillNeed,
+    DontNeed,
+    NoReuse,
+}
+
+bitflags! {
+    /// memfd seal flags, mirroring the `F_SEAL_*` constants accepted by
+    /// `fcntl(F_ADD_SEALS)`.
+    pub struct SealFlags: i32 {
+        const SEAL = 0x0001;
+        const SHRINK = 0x0002;
+        const GROW = 0x0004;
+        const WRITE = 0x0008;
+    }
+}
+
 pub struct FileMeta {
     pub inode: Option<Arc<dyn Inode>>,
     pub flags: Mutex<OpenFlags>,
+    pub locks: LockList,
+    pub advice: Mutex<Advice>,
+    pub seals: Mutex<SealFlags>,
+    /// Number of currently-mapped writable shared mappings of this file,
+    /// maintained by the mmap path via [`FileMeta::map_writable`] and
+    /// [`FileMeta::unmap_writable`]. Consulted when sealing `WRITE`.
+    pub writable_mappings: AtomicUsize,
 }
 
 impl FileMeta {
@@ -21,8 +71,31 @@ impl FileMeta {
         FileMeta {
             inode,
             flags: Mutex::new(flags),
+            locks: LockList::default(),
+            advice: Mutex::new(Advice::Normal),
+            seals: Mutex::new(SealFlags::empty()),
+            writable_mappings: AtomicUsize::new(0),
         }
     }
+
+    /// Registers a new writable shared mapping of this file. The mmap path
+    /// must call this before establishing a `MAP_SHARED` + `PROT_WRITE`
+    /// mapping so a concurrent `F_ADD_SEALS(F_SEAL_WRITE)` can see it.
+    pub fn map_writable(&self) -> SyscallResult {
+        // Hold the seals lock across the increment: add_seals reads the
+        // counter under the same lock, so it cannot slip in between.
+        let seals = self.seals.lock();
+        if seals.contains(SealFlags::WRITE) {
+            return Err(Errno::EPERM);
+        }
+        self.writable_mappings.fetch_add(1, Ordering::AcqRel);
+        Ok(())
+    }
+
+    /// Unregisters a writable shared mapping torn down by the mmap path.
+    pub fn unmap_writable(&self) {
+        self.writable_mappings.fetch_sub(1, Ordering::AcqRel);
+    }
 }
 
 /// https://man7.org/linux/man-pages/man2/lseek.2.html
@@ -65,10 +138,83 @@ pub trait File: Send + Sync {
         Err(Errno::EOPNOTSUPP)
     }
 
+    /// Backs `readv(2)`. The default loops over [`File::read`]; overriders
+    /// should take their offset lock once for the whole iovec so the combined
+    /// transfer advances the offset atomically.
+    async fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> SyscallResult<isize> {
+        let mut total = 0isize;
+        for buf in bufs.iter_mut() {
+            let count = self.read(buf).await?;
+            total += count;
+            if (count as usize) < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Backs `writev(2)`. See [`File::read_vectored`] for the offset-locking
+    /// contract overriders must uphold.
+    async fn write_vectored(&self, bufs: &[&[u8]]) -> SyscallResult<isize> {
+        let mut total = 0isize;
+        for buf in bufs.iter() {
+            let count = self.write(buf).await?;
+            total += count;
+            if (count as usize) < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
     async fn truncate(&self, size: isize) -> SyscallResult {
         Err(Errno::EOPNOTSUPP)
     }
 
+    async fn fallocate(&self, mode: FallocMode, offset: isize, len: isize) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    async fn set_lock(&self, cmd: LockCmd, lock: FileLock) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    async fn get_lock(&self, lock: &mut FileLock) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Releases every lock this file holds on behalf of `owner`. Called from
+    /// the close path when `owner`'s last fd referring to this file is closed.
+    async fn release_locks(&self, owner: Pid) {
+    }
+
+    /// Called once the fd for this file is established. Backs the inotify
+    /// `OPEN` event.
+    fn on_open(&self) {
+    }
+
+    /// Called from the close path when this fd is closed, with `writable`
+    /// set if it was opened for writing. Backs the inotify
+    /// `CLOSE_WRITE`/`CLOSE_NOWRITE` events.
+    async fn on_close(&self, writable: bool) {
+    }
+
+    /// Backs `posix_fadvise(2)`. Advisory only, so backends that don't
+    /// implement a particular hint default to a no-op success.
+    async fn fadvise(&self, offset: isize, len: isize, advice: Advice) -> SyscallResult {
+        Ok(())
+    }
+
+    /// Backs `fcntl(F_ADD_SEALS)`, reachable through `ioctl`/`fcntl`.
+    fn add_seals(&self, seals: SealFlags) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Backs `fcntl(F_GET_SEALS)`.
+    fn get_seals(&self) -> SyscallResult<SealFlags> {
+        Err(Errno::EOPNOTSUPP)
+    }
+
     async fn sync(&self) -> SyscallResult {
         Err(Errno::EOPNOTSUPP)
     }
@@ -154,6 +300,36 @@ impl File for CharacterFile {
         Ok(count)
     }
 
+    async fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> SyscallResult<isize> {
+        let inode = self.metadata.inode.as_ref().unwrap();
+        let mut pos = self.pos.lock().await;
+        let mut total = 0isize;
+        for buf in bufs.iter_mut() {
+            let count = inode.read(buf, *pos).await?;
+            *pos += count;
+            total += count;
+            if (count as usize) < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    async fn write_vectored(&self, bufs: &[&[u8]]) -> SyscallResult<isize> {
+        let inode = self.metadata.inode.as_ref().unwrap();
+        let mut pos = self.pos.lock().await;
+        let mut total = 0isize;
+        for buf in bufs.iter() {
+            let count = inode.write(buf, *pos).await?;
+            *pos += count;
+            total += count;
+            if (count as usize) < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
     async fn ioctl(&self, request: usize, value: usize, arg2: usize, arg3: usize, arg4: usize) -> SyscallResult<i32> {
         let inode = self.metadata.inode.as_ref().unwrap();
         inode.ioctl(request, value, arg2, arg3, arg4)
@@ -188,6 +364,9 @@ impl File for DirFile {
             return Err(Errno::ENOENT);
         }
         let mut pos = self.pos.lock().await;
+        if *pos == 0 {
+            inode.metadata().watches.notify(InotifyMask::ACCESS, 0, None);
+        }
         let inode = match *pos {
             0 => inode.clone(),
             1 => inode.metadata().parent.clone().and_then(|p| p.upgrade()).unwrap_or(inode.clone()),
@@ -220,6 +399,27 @@ impl RegularFile {
             prw_lock: AsyncMutex::default(),
         })
     }
+
+    fn check_write_sealed(&self) -> SyscallResult {
+        if self.metadata.seals.lock().contains(SealFlags::WRITE) {
+            return Err(Errno::EPERM);
+        }
+        Ok(())
+    }
+
+    fn check_shrink_sealed(&self) -> SyscallResult {
+        if self.metadata.seals.lock().contains(SealFlags::SHRINK) {
+            return Err(Errno::EPERM);
+        }
+        Ok(())
+    }
+
+    fn check_grow_sealed(&self) -> SyscallResult {
+        if self.metadata.seals.lock().contains(SealFlags::GROW) {
+            return Err(Errno::EPERM);
+        }
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -233,24 +433,192 @@ impl File for RegularFile {
         let mut pos = self.pos.lock().await;
         let count = inode.read(buf, *pos).await?;
         *pos += count;
+        match *self.metadata.advice.lock() {
+            Advice::Random => {}
+            Advice::Sequential | Advice::WillNeed => {
+                let _ = inode.readahead(*pos, READAHEAD_WINDOW * 4).await;
+            }
+            Advice::Normal | Advice::DontNeed | Advice::NoReuse => {
+                let _ = inode.readahead(*pos, READAHEAD_WINDOW).await;
+            }
+        }
         Ok(count)
     }
 
     async fn write(&self, buf: &[u8]) -> SyscallResult<isize> {
+        self.check_write_sealed()?;
         let inode = self.metadata.inode.as_ref().unwrap();
         let mut pos = self.pos.lock().await;
+        if *pos + buf.len() as isize > inode.metadata().inner.lock().size {
+            self.check_grow_sealed()?;
+        }
         let count = inode.write(buf, *pos).await?;
         *pos += count;
+        inode.metadata().watches.notify(InotifyMask::MODIFY, 0, None);
         Ok(count)
     }
 
+    async fn read_vectored(&self, bufs: &mut [&mut [u8]]) -> SyscallResult<isize> {
+        let inode = self.metadata.inode.as_ref().unwrap();
+        let mut pos = self.pos.lock().await;
+        let mut total = 0isize;
+        for buf in bufs.iter_mut() {
+            let count = inode.read(buf, *pos).await?;
+            *pos += count;
+            total += count;
+            if (count as usize) < buf.len() {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    async fn write_vectored(&self, bufs: &[&[u8]]) -> SyscallResult<isize> {
+        self.check_write_sealed()?;
+        let inode = self.metadata.inode.as_ref().unwrap();
+        let mut pos = self.pos.lock().await;
+        let mut total = 0isize;
+        for buf in bufs.iter() {
+            if *pos + buf.len() as isize > inode.metadata().inner.lock().size {
+                self.check_grow_sealed()?;
+            }
+            let count = inode.write(buf, *pos).await?;
+            *pos += count;
+            total += count;
+            if (count as usize) < buf.len() {
+                break;
+            }
+        }
+        if total > 0 {
+            inode.metadata().watches.notify(InotifyMask::MODIFY, 0, None);
+        }
+        Ok(total)
+    }
+
     async fn truncate(&self, size: isize) -> SyscallResult {
+        self.check_write_sealed()?;
         let inode = self.metadata.inode.as_ref().unwrap();
+        let current_size = inode.metadata().inner.lock().size;
+        if size < current_size {
+            self.check_shrink_sealed()?;
+        } else if size > current_size {
+            self.check_grow_sealed()?;
+        }
         inode.truncate(size).await?;
+        inode.metadata().watches.notify(InotifyMask::MODIFY, 0, None);
         // The value of the seek pointer shall not be modified by a call to ftruncate().
         Ok(())
     }
 
+    async fn fallocate(&self, mode: FallocMode, offset: isize, len: isize) -> SyscallResult {
+        if offset < 0 || len <= 0 {
+            return Err(Errno::EINVAL);
+        }
+        self.check_write_sealed()?;
+        let inode = self.metadata.inode.as_ref().unwrap();
+        let aligned = |n: isize| n % PAGE_SIZE as isize == 0;
+        let keep_size = mode.contains(FallocMode::KEEP_SIZE);
+        // KEEP_SIZE is a modifier, not a mode of its own; strip it before
+        // deciding which of the mutually-exclusive modes below applies.
+        let op = mode & !FallocMode::KEEP_SIZE;
+
+        if op == FallocMode::COLLAPSE_RANGE {
+            if keep_size || !aligned(offset) || !aligned(len) {
+                return Err(Errno::EINVAL);
+            }
+            let size = inode.metadata().inner.lock().size;
+            if offset + len >= size {
+                return Err(Errno::EINVAL);
+            }
+            self.check_shrink_sealed()?;
+            return inode.collapse_range(offset, len).await;
+        }
+        if op == FallocMode::INSERT_RANGE {
+            if keep_size || !aligned(offset) || !aligned(len) {
+                return Err(Errno::EINVAL);
+            }
+            self.check_grow_sealed()?;
+            return inode.insert_range(offset, len).await;
+        }
+        if op == FallocMode::PUNCH_HOLE {
+            if !keep_size {
+                return Err(Errno::EINVAL);
+            }
+            return inode.punch_hole(offset, len).await;
+        }
+        if op == FallocMode::ZERO_RANGE {
+            if !keep_size {
+                let size = inode.metadata().inner.lock().size;
+                if offset + len > size {
+                    self.check_grow_sealed()?;
+                }
+            }
+            return inode.zero_range(offset, len, keep_size).await;
+        }
+        if !op.is_empty() {
+            return Err(Errno::EOPNOTSUPP);
+        }
+        if !keep_size {
+            self.check_grow_sealed()?;
+        }
+        inode.fallocate(offset, len, keep_size).await
+    }
+
+    fn add_seals(&self, seals: SealFlags) -> SyscallResult {
+        let mut current = self.metadata.seals.lock();
+        if current.contains(SealFlags::SEAL) {
+            return Err(Errno::EPERM);
+        }
+        if seals.contains(SealFlags::WRITE)
+            && self.metadata.writable_mappings.load(Ordering::Acquire) > 0
+        {
+            return Err(Errno::EBUSY);
+        }
+        *current |= seals;
+        drop(current);
+        let inode = self.metadata.inode.as_ref().unwrap();
+        inode.metadata().watches.notify(InotifyMask::ATTRIB, 0, None);
+        Ok(())
+    }
+
+    fn get_seals(&self) -> SyscallResult<SealFlags> {
+        Ok(*self.metadata.seals.lock())
+    }
+
+    async fn set_lock(&self, cmd: LockCmd, lock: FileLock) -> SyscallResult {
+        self.metadata.locks.set(cmd, lock).await
+    }
+
+    async fn get_lock(&self, lock: &mut FileLock) -> SyscallResult {
+        self.metadata.locks.get(lock);
+        Ok(())
+    }
+
+    async fn release_locks(&self, owner: Pid) {
+        self.metadata.locks.release_all(owner);
+    }
+
+    fn on_open(&self) {
+        let inode = self.metadata.inode.as_ref().unwrap();
+        inode.metadata().watches.notify(InotifyMask::OPEN, 0, None);
+    }
+
+    async fn on_close(&self, writable: bool) {
+        let inode = self.metadata.inode.as_ref().unwrap();
+        let mask = if writable { InotifyMask::CLOSE_WRITE } else { InotifyMask::CLOSE_NOWRITE };
+        inode.metadata().watches.notify(mask, 0, None);
+    }
+
+    async fn fadvise(&self, offset: isize, len: isize, advice: Advice) -> SyscallResult {
+        *self.metadata.advice.lock() = advice;
+        let inode = self.metadata.inode.as_ref().unwrap();
+        match advice {
+            Advice::Sequential | Advice::WillNeed => inode.readahead(offset, len).await,
+            Advice::DontNeed => inode.drop_cache(offset, len).await,
+            Advice::Normal | Advice::Random | Advice::NoReuse => Ok(()),
+        }
+    }
+
     async fn sync(&self) -> SyscallResult {
         let inode = self.metadata.inode.as_ref().unwrap();
         inode.sync().await?;
@@ -301,3 +669,64 @@ impl File for RegularFile {
         ret
     }
 }
+
+/// The file returned by `inotify_init(2)`. Holds the combined, ordered
+/// queue of events raised by every inode this instance watches.
+pub struct InotifyFile {
+    metadata: FileMeta,
+    queue: Arc<EventQueue>,
+    next_wd: Mutex<Wd>,
+}
+
+impl InotifyFile {
+    pub fn new(metadata: FileMeta) -> Arc<Self> {
+        Arc::new(Self {
+            metadata,
+            queue: Arc::default(),
+            next_wd: Mutex::new(1),
+        })
+    }
+
+    /// Registers a watch for `mask` against `inode`, returning its watch
+    /// descriptor.
+    pub fn add_watch(self: &Arc<Self>, inode: &Arc<dyn Inode>, mask: InotifyMask) -> Wd {
+        let mut next_wd = self.next_wd.lock();
+        let wd = *next_wd;
+        *next_wd += 1;
+        let sink: Arc<dyn NotifySink> = self.clone();
+        inode.metadata().watches.add(wd, mask, &sink);
+        wd
+    }
+
+    pub fn rm_watch(self: &Arc<Self>, inode: &Arc<dyn Inode>, wd: Wd) -> SyscallResult {
+        let sink: Arc<dyn NotifySink> = self.clone();
+        inode.metadata().watches.remove(wd, &sink)
+    }
+}
+
+impl NotifySink for InotifyFile {
+    fn notify(&self, wd: Wd, mask: InotifyMask, cookie: u32, name: Option<String>) {
+        self.queue.push(InotifyEvent { wd, mask, cookie, name });
+    }
+}
+
+#[async_trait]
+impl File for InotifyFile {
+    fn metadata(&self) -> &FileMeta {
+        &self.metadata
+    }
+
+    async fn read(&self, buf: &mut [u8]) -> SyscallResult<isize> {
+        self.queue.drain_into(buf)
+    }
+
+    fn pollin(&self, waker: Option<Waker>) -> SyscallResult<bool> {
+        if !self.queue.is_empty() {
+            return Ok(true);
+        }
+        if let Some(waker) = waker {
+            self.queue.register_waker(waker);
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,103 @@
+use alloc::sync::{Arc, Weak};
+use async_trait::async_trait;
+use crate::fs::notify::WatchList;
+use crate::process::thread::Audit;
+use crate::result::{Errno, SyscallResult};
+use crate::sync::mutex::Mutex;
+
+pub struct InodeInner {
+    pub size: isize,
+    pub unlinked: bool,
+}
+
+pub struct InodeMeta {
+    pub parent: Option<Weak<dyn Inode>>,
+    pub inner: Mutex<InodeInner>,
+    pub watches: WatchList,
+}
+
+impl InodeMeta {
+    pub fn new(parent: Option<Weak<dyn Inode>>, size: isize) -> Self {
+        InodeMeta {
+            parent,
+            inner: Mutex::new(InodeInner { size, unlinked: false }),
+            watches: WatchList::default(),
+        }
+    }
+}
+
+#[allow(unused)]
+#[async_trait]
+pub trait Inode: Send + Sync {
+    fn metadata(&self) -> &InodeMeta;
+
+    async fn read(&self, buf: &mut [u8], offset: isize) -> SyscallResult<isize> {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    async fn write(&self, buf: &[u8], offset: isize) -> SyscallResult<isize> {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    async fn truncate(&self, size: isize) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    async fn sync(&self) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    fn ioctl(&self, request: usize, value: usize, arg2: usize, arg3: usize, arg4: usize) -> SyscallResult<i32> {
+        Err(Errno::ENOTTY)
+    }
+
+    async fn lookup_idx(self: Arc<Self>, idx: usize, audit: &Audit) -> SyscallResult<Arc<dyn Inode>> {
+        Err(Errno::ENOENT)
+    }
+
+    /// Allocates backing blocks for `[offset, offset + len)`, extending the
+    /// inode size to `offset + len` unless `keep_size` is set. Backs the
+    /// default (mode 0) `fallocate(2)` behavior.
+    async fn fallocate(&self, offset: isize, len: isize, keep_size: bool) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Zeroes `[offset, offset + len)` and frees the underlying blocks
+    /// without changing the inode size. Backs `FALLOC_FL_PUNCH_HOLE`.
+    async fn punch_hole(&self, offset: isize, len: isize) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Zeroes `[offset, offset + len)`; the underlying blocks may stay
+    /// allocated. Extends the inode size to `offset + len` unless
+    /// `keep_size` is set. Backs `FALLOC_FL_ZERO_RANGE`.
+    async fn zero_range(&self, offset: isize, len: isize, keep_size: bool) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Removes `[offset, offset + len)` and shifts all following bytes left,
+    /// shrinking the inode by `len`. Backs `FALLOC_FL_COLLAPSE_RANGE`.
+    async fn collapse_range(&self, offset: isize, len: isize) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Inserts a zero-filled hole of `len` bytes at `offset`, shifting
+    /// following data right and growing the inode by `len`. Backs
+    /// `FALLOC_FL_INSERT_RANGE`.
+    async fn insert_range(&self, offset: isize, len: isize) -> SyscallResult {
+        Err(Errno::EOPNOTSUPP)
+    }
+
+    /// Prefetches `[offset, offset + len)` into the page cache ahead of an
+    /// anticipated read. Backs `posix_fadvise(POSIX_FADV_WILLNEED)` and the
+    /// read-path readahead triggered by `POSIX_FADV_SEQUENTIAL`.
+    async fn readahead(&self, offset: isize, len: isize) -> SyscallResult {
+        Ok(())
+    }
+
+    /// Drops clean cached pages covering `[offset, offset + len)`. Backs
+    /// `posix_fadvise(POSIX_FADV_DONTNEED)`.
+    async fn drop_cache(&self, offset: isize, len: isize) -> SyscallResult {
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use crate::result::{Errno, SyscallResult};
+use crate::sync::mutex::Mutex;
+
+/// Identifies the process that owns a [`FileLock`].
+pub type Pid = usize;
+
+/// The `fcntl(2)` lock command, mirroring `F_SETLK`/`F_SETLKW`/`F_GETLK`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockCmd {
+    SetLk,
+    SetLkw,
+    GetLk,
+}
+
+/// The kind of a byte-range lock, mirroring `F_RDLCK`/`F_WRLCK`/`F_UNLCK`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockKind {
+    Read,
+    Write,
+    Unlock,
+}
+
+/// A POSIX advisory byte-range lock record.
+///
+/// `end == None` means the lock extends to the current end of file.
+#[derive(Debug, Clone, Copy)]
+pub struct FileLock {
+    pub owner: Pid,
+    pub kind: LockKind,
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+impl FileLock {
+    fn overlaps(&self, other: &FileLock) -> bool {
+        let self_end = self.end.unwrap_or(u64::MAX);
+        let other_end = other.end.unwrap_or(u64::MAX);
+        self.start < other_end && other.start < self_end
+    }
+
+    /// Two locks conflict if their ranges overlap, they belong to different
+    /// owners, and at least one of them is a write lock.
+    fn conflicts(&self, other: &FileLock) -> bool {
+        self.owner != other.owner
+            && self.kind != LockKind::Unlock
+            && other.kind != LockKind::Unlock
+            && (self.kind == LockKind::Write || other.kind == LockKind::Write)
+            && self.overlaps(other)
+    }
+}
+
+#[derive(Default)]
+struct LockListInner {
+    locks: Vec<FileLock>,
+    wakers: Vec<Waker>,
+}
+
+/// The list of advisory locks held against a single file, plus a waker queue
+/// for tasks blocked in `F_SETLKW`.
+#[derive(Default)]
+pub struct LockList {
+    inner: Mutex<LockListInner>,
+}
+
+impl LockList {
+    /// Applies `set(lock)`, waiting for conflicting locks to clear when
+    /// `cmd` is [`LockCmd::SetLkw`].
+    pub async fn set(&self, cmd: LockCmd, lock: FileLock) -> SyscallResult {
+        loop {
+            {
+                let mut inner = self.inner.lock();
+                if !inner.locks.iter().any(|l| l.conflicts(&lock)) {
+                    Self::apply(&mut inner.locks, lock);
+                    // Releasing or downgrading a range can unblock tasks
+                    // parked in F_SETLKW on an overlapping range; wake them
+                    // here rather than only on fd close (see release_all).
+                    for waker in inner.wakers.drain(..) {
+                        waker.wake();
+                    }
+                    return Ok(());
+                }
+                if cmd == LockCmd::SetLk {
+                    return Err(Errno::EAGAIN);
+                }
+            }
+            LockWait { list: self, lock }.await;
+        }
+    }
+
+    /// Fills `lock` in with the first lock that would conflict with it, or
+    /// sets its `kind` to [`LockKind::Unlock`] if none would.
+    pub fn get(&self, lock: &mut FileLock) {
+        let inner = self.inner.lock();
+        match inner.locks.iter().find(|l| l.conflicts(lock)) {
+            Some(conflict) => *lock = *conflict,
+            None => lock.kind = LockKind::Unlock,
+        }
+    }
+
+    /// Drops every lock owned by `owner`, waking any tasks parked in
+    /// `F_SETLKW` so they can re-check for remaining conflicts.
+    pub fn release_all(&self, owner: Pid) {
+        let mut inner = self.inner.lock();
+        inner.locks.retain(|l| l.owner != owner);
+        for waker in inner.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Splits and coalesces `locks` so that `new` replaces whatever `new.owner`
+    /// previously held in `[new.start, new.end)`. Waking parked waiters is
+    /// left to the caller.
+    fn apply(locks: &mut Vec<FileLock>, new: FileLock) {
+        let mut merged = Vec::with_capacity(locks.len() + 1);
+        for existing in locks.drain(..) {
+            if existing.owner != new.owner || !existing.overlaps(&new) {
+                merged.push(existing);
+                continue;
+            }
+            if existing.start < new.start {
+                merged.push(FileLock { end: Some(new.start), ..existing });
+            }
+            match (existing.end, new.end) {
+                (Some(existing_end), Some(new_end)) if existing_end > new_end => {
+                    merged.push(FileLock { start: new_end, ..existing });
+                }
+                (None, Some(new_end)) => {
+                    merged.push(FileLock { start: new_end, end: None, ..existing });
+                }
+                _ => {}
+            }
+        }
+        if new.kind != LockKind::Unlock {
+            merged.push(new);
+        }
+        merged.sort_by_key(|l| l.start);
+        // Adjacent ranges of the same owner/kind must coalesce even when a
+        // different owner's non-conflicting lock sorts between them, so scan
+        // for a mergeable candidate anywhere already placed, not just the
+        // immediately preceding entry.
+        let mut coalesced: Vec<FileLock> = Vec::with_capacity(merged.len());
+        for lock in merged {
+            let mergeable = coalesced.iter_mut().rev().find(|existing| {
+                existing.owner == lock.owner
+                    && existing.kind == lock.kind
+                    && existing.end.is_some_and(|end| end >= lock.start)
+            });
+            if let Some(existing) = mergeable {
+                existing.end = match (existing.end, lock.end) {
+                    (Some(a), Some(b)) => Some(a.max(b)),
+                    _ => None,
+                };
+                continue;
+            }
+            coalesced.push(lock);
+        }
+        *locks = coalesced;
+    }
+}
+
+/// Future that resolves once `lock` no longer conflicts with anything in
+/// `list`, registering the waker for [`LockList::set`] or
+/// [`LockList::release_all`] to wake.
+struct LockWait<'a> {
+    list: &'a LockList,
+    lock: FileLock,
+}
+
+impl Future for LockWait<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut inner = self.list.inner.lock();
+        if inner.locks.iter().any(|l| l.conflicts(&self.lock)) {
+            inner.wakers.push(cx.waker().clone());
+            Poll::Pending
+        } else {
+            Poll::Ready(())
+        }
+    }
+}
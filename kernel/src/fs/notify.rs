@@ -0,0 +1,178 @@
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::mem::size_of;
+use core::task::Waker;
+use bitflags::bitflags;
+use crate::result::{Errno, SyscallResult};
+use crate::sync::mutex::Mutex;
+
+bitflags! {
+    /// inotify event mask, mirroring the `IN_*` constants.
+    pub struct InotifyMask: u32 {
+        const ACCESS = 0x0000_0001;
+        const MODIFY = 0x0000_0002;
+        const ATTRIB = 0x0000_0004;
+        const CLOSE_WRITE = 0x0000_0008;
+        const CLOSE_NOWRITE = 0x0000_0010;
+        const OPEN = 0x0000_0020;
+        const MOVED_FROM = 0x0000_0040;
+        const MOVED_TO = 0x0000_0080;
+        const CREATE = 0x0000_0100;
+        const DELETE = 0x0000_0200;
+        const DELETE_SELF = 0x0000_0400;
+        const MOVE_SELF = 0x0000_0800;
+    }
+}
+
+/// A watch descriptor, unique within the inotify instance that issued it.
+pub type Wd = i32;
+
+/// A single queued inotify record, in the shape of `struct inotify_event`.
+pub struct InotifyEvent {
+    pub wd: Wd,
+    pub mask: InotifyMask,
+    pub cookie: u32,
+    pub name: Option<String>,
+}
+
+impl InotifyEvent {
+    /// Header size of `struct inotify_event`, excluding the trailing name.
+    const HEADER_LEN: usize = size_of::<i32>() * 3 + size_of::<u32>();
+
+    /// Total length of this event once encoded, including name padding.
+    pub fn encoded_len(&self) -> usize {
+        Self::HEADER_LEN + Self::padded_name_len(&self.name)
+    }
+
+    fn padded_name_len(name: &Option<String>) -> usize {
+        match name {
+            Some(name) => (name.len() + 1 + 7) & !7,
+            None => 0,
+        }
+    }
+
+    /// Appends this event's binary `struct inotify_event` layout to `buf`.
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        let name_len = Self::padded_name_len(&self.name);
+        buf.extend_from_slice(&self.wd.to_ne_bytes());
+        buf.extend_from_slice(&self.mask.bits().to_ne_bytes());
+        buf.extend_from_slice(&self.cookie.to_ne_bytes());
+        buf.extend_from_slice(&(name_len as u32).to_ne_bytes());
+        if let Some(name) = &self.name {
+            let start = buf.len();
+            buf.extend_from_slice(name.as_bytes());
+            buf.resize(start + name_len, 0);
+        }
+    }
+}
+
+/// Receives events forwarded from a [`WatchList`]; implemented by the
+/// inotify instance (the open file returned by `inotify_init(2)`).
+pub trait NotifySink: Send + Sync {
+    fn notify(&self, wd: Wd, mask: InotifyMask, cookie: u32, name: Option<String>);
+}
+
+struct Watch {
+    wd: Wd,
+    mask: InotifyMask,
+    sink: Weak<dyn NotifySink>,
+}
+
+/// The set of watches registered against a single inode. Mutation points in
+/// the VFS call [`WatchList::notify`] to fan an event out to every matching
+/// watch.
+#[derive(Default)]
+pub struct WatchList {
+    watches: Mutex<Vec<Watch>>,
+}
+
+impl WatchList {
+    pub fn add(&self, wd: Wd, mask: InotifyMask, sink: &Arc<dyn NotifySink>) {
+        self.watches.lock().push(Watch { wd, mask, sink: Arc::downgrade(sink) });
+    }
+
+    /// Removes the watch `wd` registered by `sink`. Watch descriptors are
+    /// only unique within the instance that allocated them, so a bare `wd`
+    /// match would risk deleting another instance's watch on the same
+    /// inode; `sink` scopes the removal to the caller's own watch.
+    pub fn remove(&self, wd: Wd, sink: &Arc<dyn NotifySink>) -> SyscallResult {
+        let target = Arc::downgrade(sink);
+        let mut watches = self.watches.lock();
+        let len_before = watches.len();
+        watches.retain(|w| !(w.wd == wd && w.sink.ptr_eq(&target)));
+        if watches.len() == len_before {
+            return Err(Errno::EINVAL);
+        }
+        Ok(())
+    }
+
+    /// Fans `mask` out to every registered watch whose mask intersects it,
+    /// dropping watches whose owning instance has since been closed.
+    pub fn notify(&self, mask: InotifyMask, cookie: u32, name: Option<&str>) {
+        self.watches.lock().retain(|watch| {
+            let Some(sink) = watch.sink.upgrade() else {
+                return false;
+            };
+            let matched = watch.mask & mask;
+            if !matched.is_empty() {
+                sink.notify(watch.wd, matched, cookie, name.map(String::from));
+            }
+            true
+        });
+    }
+}
+
+/// Queue of undelivered events for a single inotify instance, plus the
+/// waker of whichever task is blocked reading it.
+#[derive(Default)]
+pub struct EventQueue {
+    inner: Mutex<EventQueueInner>,
+}
+
+#[derive(Default)]
+struct EventQueueInner {
+    events: VecDeque<InotifyEvent>,
+    waker: Option<Waker>,
+}
+
+impl EventQueue {
+    pub fn push(&self, event: InotifyEvent) {
+        let mut inner = self.inner.lock();
+        inner.events.push_back(event);
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().events.is_empty()
+    }
+
+    pub fn register_waker(&self, waker: Waker) {
+        self.inner.lock().waker = Some(waker);
+    }
+
+    /// Drains as many whole events as fit into `buf`, returning the number
+    /// of bytes written. Returns `Err(EINVAL)` if `buf` is too small to hold
+    /// even the next pending event.
+    pub fn drain_into(&self, buf: &mut [u8]) -> SyscallResult<isize> {
+        let mut inner = self.inner.lock();
+        let mut encoded = Vec::new();
+        let mut written = 0usize;
+        while let Some(event) = inner.events.front() {
+            if written + event.encoded_len() > buf.len() {
+                break;
+            }
+            let event = inner.events.pop_front().unwrap();
+            event.encode(&mut encoded);
+            written = encoded.len();
+        }
+        if written == 0 && !inner.events.is_empty() {
+            return Err(Errno::EINVAL);
+        }
+        buf[..written].copy_from_slice(&encoded);
+        Ok(written as isize)
+    }
+}